@@ -0,0 +1,62 @@
+use actix_web::http::header::{HeaderMap, HeaderValue, CONTENT_ENCODING};
+use std::io::Read;
+
+// Clients may opt into compressing collab payloads on the wire by sending this value in the
+// `Content-Encoding` request header. Requests that omit the header keep using the raw encoding
+// so existing clients, and `batch_create_collab_compatibility_with_uncompress_params_test`,
+// keep working unmodified.
+//
+// Only the write path (decompressing an incoming batch) is implemented here. Responding to
+// `Accept-Encoding: zstd` on reads would need a GET-collab response handler, which isn't part
+// of this source snapshot — add `compress_for_response`/wiring there if that handler lands.
+pub const ZSTD_CONTENT_ENCODING: &str = "zstd";
+
+// Well above the ~1MB collabs moved in the batch tests, bounding how much a single
+// `Content-Encoding: zstd` body can expand to so a small malicious payload can't decompress
+// into gigabytes and OOM the server (a "zip bomb" for zstd).
+const MAX_DECOMPRESSED_BYTES: u64 = 64 * 1024 * 1024;
+
+pub fn content_encoding_is_zstd(headers: &HeaderMap) -> bool {
+  headers
+    .get(CONTENT_ENCODING)
+    .and_then(|v: &HeaderValue| v.to_str().ok())
+    .map(|v| v.eq_ignore_ascii_case(ZSTD_CONTENT_ENCODING))
+    .unwrap_or(false)
+}
+
+// Ratio of compressed to original bytes for a transfer, for feeding straight into
+// `CollabMetrics::record_compression_ratio`. Returns `None` when `original_len` is 0 to avoid
+// a division by zero skewing the gauge.
+pub fn compression_ratio(compressed_len: usize, original_len: usize) -> Option<f64> {
+  if original_len == 0 {
+    return None;
+  }
+  Some(compressed_len as f64 / original_len as f64)
+}
+
+// Decompresses a request body when it declares `Content-Encoding: zstd`; otherwise returns it
+// unchanged. Reads through a bounded decoder rather than `zstd::stream::decode_all`, which
+// allocates unboundedly, so a payload that expands past `MAX_DECOMPRESSED_BYTES` is rejected
+// instead of exhausting server memory.
+pub fn decompress_if_negotiated(body: Vec<u8>, headers: &HeaderMap) -> std::io::Result<Vec<u8>> {
+  if !content_encoding_is_zstd(headers) {
+    return Ok(body);
+  }
+
+  let decoder = zstd::stream::read::Decoder::new(body.as_slice())?;
+  let mut limited = decoder.take(MAX_DECOMPRESSED_BYTES + 1);
+  let mut out = Vec::new();
+  limited.read_to_end(&mut out)?;
+
+  if out.len() as u64 > MAX_DECOMPRESSED_BYTES {
+    return Err(std::io::Error::new(
+      std::io::ErrorKind::InvalidData,
+      format!(
+        "decompressed collab payload exceeds max allowed size of {} bytes",
+        MAX_DECOMPRESSED_BYTES
+      ),
+    ));
+  }
+
+  Ok(out)
+}
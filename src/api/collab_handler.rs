@@ -0,0 +1,124 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use actix_web::web::{Bytes, BytesMut, Data, Payload};
+use actix_web::{web, HttpRequest, HttpResponse, Result, Scope};
+use collab_entity::CollabType;
+use database_entity::dto::{BatchCreateCollabParams, CollabParams};
+use futures_util::StreamExt;
+
+use crate::api::collab_compression::{compression_ratio, content_encoding_is_zstd, decompress_if_negotiated};
+use crate::api::collab_stream::{decode_collab_stream, STREAM_CONTENT_TYPE, STREAM_QUERY_FLAG};
+use crate::api::metrics::AppFlowyCloudMetrics;
+
+// Persists (and, for the streaming path, rolls back) collabs for a workspace. Storage is
+// injected behind a trait since this source snapshot doesn't include the crate's concrete
+// database implementation.
+#[async_trait::async_trait]
+pub trait CollabBatchStorage: Send + Sync {
+  async fn insert_collab(&self, workspace_id: &str, params: CollabParams) -> Result<()>;
+  async fn insert_collabs(&self, workspace_id: &str, params: Vec<CollabParams>) -> Result<()>;
+  // Compensating action for the streaming path: removes collabs that were already persisted
+  // one-by-one before a later frame turned out malformed or oversized.
+  async fn delete_collabs(&self, workspace_id: &str, object_ids: Vec<String>) -> Result<()>;
+}
+
+pub fn collabs_scope() -> Scope {
+  web::scope("/api/workspace/{workspace_id}/collabs")
+    .service(web::resource("").route(web::post().to(create_collabs_batch)))
+}
+
+fn wants_streaming_ingestion(req: &HttpRequest) -> bool {
+  let is_stream_content_type = req.content_type().eq_ignore_ascii_case(STREAM_CONTENT_TYPE);
+  let has_stream_flag = web::Query::<std::collections::HashMap<String, String>>::from_query(req.query_string())
+    .ok()
+    .and_then(|query| query.get(STREAM_QUERY_FLAG).cloned())
+    .map(|value| value == "1")
+    .unwrap_or(false);
+  is_stream_content_type || has_stream_flag
+}
+
+// Handles `POST /api/workspace/:workspace_id/collabs`. Streams the request body
+// (`STREAM_CONTENT_TYPE` or `?stream=1`) through `decode_collab_stream`, otherwise falls back
+// to buffering the whole body and decoding it as a `BatchCreateCollabParams`, transparently
+// decompressing it first when the client sent `Content-Encoding: zstd`.
+async fn create_collabs_batch(
+  req: HttpRequest,
+  workspace_id: web::Path<String>,
+  mut payload: Payload,
+  metrics: Data<Arc<AppFlowyCloudMetrics>>,
+  storage: Data<Arc<dyn CollabBatchStorage>>,
+) -> Result<HttpResponse> {
+  let workspace_id = workspace_id.into_inner();
+
+  if wants_streaming_ingestion(&req) {
+    let metrics = metrics.get_ref().clone();
+    let storage = storage.get_ref().clone();
+    let insert_storage = storage.clone();
+    let insert_workspace_id = workspace_id.clone();
+    let rollback_storage = storage.clone();
+    let rollback_workspace_id = workspace_id.clone();
+
+    decode_collab_stream(
+      payload,
+      metrics,
+      move |params| {
+        let storage = insert_storage.clone();
+        let workspace_id = insert_workspace_id.clone();
+        async move {
+          if let Err(err) = storage.insert_collab(&workspace_id, params).await {
+            tracing::error!("failed to persist streamed collab: {:?}", err);
+          }
+        }
+      },
+      move |object_ids| {
+        let storage = rollback_storage.clone();
+        let workspace_id = rollback_workspace_id.clone();
+        async move {
+          if let Err(err) = storage.delete_collabs(&workspace_id, object_ids).await {
+            tracing::error!("failed to roll back partially streamed collab batch: {:?}", err);
+          }
+        }
+      },
+    )
+    .await
+    .map_err(actix_web::error::ErrorBadRequest)?;
+    return Ok(HttpResponse::Ok().finish());
+  }
+
+  let mut body = BytesMut::new();
+  while let Some(chunk) = payload.next().await {
+    let chunk: Bytes = chunk?;
+    body.extend_from_slice(&chunk);
+  }
+  let was_zstd_negotiated = content_encoding_is_zstd(req.headers());
+  let compressed_len = body.len();
+  let body =
+    decompress_if_negotiated(body.to_vec(), req.headers()).map_err(actix_web::error::ErrorBadRequest)?;
+
+  let params = BatchCreateCollabParams::from_bytes(&body).map_err(actix_web::error::ErrorBadRequest)?;
+  // A batch can mix CollabTypes, so the batch-size and compression-ratio metrics are attributed
+  // per type actually present, matching the grouping `decode_collab_stream` uses for the
+  // streaming path.
+  let mut counts_by_type: HashMap<String, (CollabType, usize)> = HashMap::new();
+  for collab_params in &params.params_list {
+    metrics
+      .collab
+      .record_collab_write(&collab_params.collab_type, collab_params.encoded_collab_v1.len());
+    counts_by_type
+      .entry(format!("{:?}", collab_params.collab_type))
+      .or_insert_with(|| (collab_params.collab_type.clone(), 0))
+      .1 += 1;
+  }
+  let ratio = was_zstd_negotiated
+    .then(|| compression_ratio(compressed_len, body.len()))
+    .flatten();
+  for (collab_type, count) in counts_by_type.into_values() {
+    metrics.collab.record_collab_batch(&collab_type, count);
+    if let Some(ratio) = ratio {
+      metrics.collab.record_compression_ratio(&collab_type, ratio);
+    }
+  }
+  storage.insert_collabs(&workspace_id, params.params_list).await?;
+  Ok(HttpResponse::Ok().finish())
+}
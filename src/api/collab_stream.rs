@@ -0,0 +1,233 @@
+use actix_web::error::PayloadError;
+use actix_web::web::Bytes;
+use collab_entity::CollabType;
+use database_entity::dto::CollabParams;
+use futures_util::{Stream, StreamExt};
+use std::collections::HashMap;
+use std::sync::Arc;
+use thiserror::Error;
+
+use crate::api::metrics::AppFlowyCloudMetrics;
+
+// Content-Type a client can send on `/api/workspace/:workspace_id/collabs` to opt into
+// streaming ingestion instead of the whole-body `BatchCreateCollabParams` decode; `?stream=1`
+// opts in the same way. Requests that send neither keep using the existing in-memory path so
+// `batch_create_collab_compatibility_with_uncompress_params_test` is unaffected.
+pub const STREAM_CONTENT_TYPE: &str = "application/x-appflowy-collab-stream";
+pub const STREAM_QUERY_FLAG: &str = "stream";
+
+// Each frame on the stream is a `u32` little-endian length prefix followed by that many bytes
+// of a bincode-encoded `CollabParams`, so each collab can be decoded as soon as its frame is
+// complete instead of buffering the whole `BatchCreateCollabParams::to_bytes` body first.
+const FRAME_LEN_PREFIX_BYTES: usize = 4;
+
+// A single collab in the batch tests tops out around 1MB; 16MB leaves headroom for larger
+// documents while still rejecting a corrupt/hostile length prefix long before it can make the
+// decoder buffer gigabytes for one frame.
+const MAX_FRAME_LEN: usize = 16 * 1024 * 1024;
+
+#[derive(Debug, Error)]
+pub enum StreamDecodeError {
+  #[error("payload error while reading collab stream: {0}")]
+  Payload(#[from] PayloadError),
+  #[error("malformed frame: {0}")]
+  Malformed(String),
+  #[error("failed to decode collab params: {0}")]
+  Decode(#[from] bincode::Error),
+}
+
+// Reads length-delimited `CollabParams` frames off an actix `Payload` stream, persisting each
+// one as it arrives via `on_collab_decoded` so peak memory stays proportional to one collab
+// plus the small `object_id`s of everything persisted so far, not the whole batch. If a later
+// frame turns out malformed or oversized, the already-persisted `object_id`s are handed to
+// `on_batch_failed` so the caller can roll them back (e.g. delete-by-id in a single statement),
+// giving the same all-or-nothing outcome as the whole-body path without buffering the batch.
+pub async fn decode_collab_stream<S, F, Fut, C, CFut>(
+  mut payload: S,
+  metrics: Arc<AppFlowyCloudMetrics>,
+  mut on_collab_decoded: F,
+  mut on_batch_failed: C,
+) -> Result<usize, StreamDecodeError>
+where
+  S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+  F: FnMut(CollabParams) -> Fut,
+  Fut: std::future::Future<Output = ()>,
+  C: FnMut(Vec<String>) -> CFut,
+  CFut: std::future::Future<Output = ()>,
+{
+  let mut buf = Vec::new();
+  let mut persisted_object_ids = Vec::new();
+  // A batch can mix CollabTypes, so the batch-size metric is attributed per type actually
+  // present rather than a single caller-supplied hint. This map is bounded by the number of
+  // distinct CollabTypes, not the batch size.
+  let mut counts_by_type: HashMap<String, (CollabType, usize)> = HashMap::new();
+
+  let decode_result = decode_frames(
+    &mut payload,
+    &mut buf,
+    &metrics,
+    &mut persisted_object_ids,
+    &mut counts_by_type,
+    &mut on_collab_decoded,
+  )
+  .await;
+
+  if let Err(err) = decode_result {
+    if !persisted_object_ids.is_empty() {
+      on_batch_failed(persisted_object_ids).await;
+    }
+    return Err(err);
+  }
+
+  for (collab_type, count) in counts_by_type.into_values() {
+    metrics.collab.record_collab_batch(&collab_type, count);
+  }
+
+  Ok(persisted_object_ids.len())
+}
+
+async fn decode_frames<S, F, Fut>(
+  payload: &mut S,
+  buf: &mut Vec<u8>,
+  metrics: &Arc<AppFlowyCloudMetrics>,
+  persisted_object_ids: &mut Vec<String>,
+  counts_by_type: &mut HashMap<String, (CollabType, usize)>,
+  on_collab_decoded: &mut F,
+) -> Result<(), StreamDecodeError>
+where
+  S: Stream<Item = Result<Bytes, PayloadError>> + Unpin,
+  F: FnMut(CollabParams) -> Fut,
+  Fut: std::future::Future<Output = ()>,
+{
+  while let Some(chunk) = payload.next().await {
+    buf.extend_from_slice(&chunk?);
+
+    loop {
+      if buf.len() < FRAME_LEN_PREFIX_BYTES {
+        break;
+      }
+      let frame_len = u32::from_le_bytes(buf[..FRAME_LEN_PREFIX_BYTES].try_into().unwrap()) as usize;
+      if frame_len > MAX_FRAME_LEN {
+        return Err(StreamDecodeError::Malformed(format!(
+          "frame length {} bytes exceeds max allowed frame size of {} bytes",
+          frame_len, MAX_FRAME_LEN
+        )));
+      }
+      if buf.len() < FRAME_LEN_PREFIX_BYTES + frame_len {
+        break;
+      }
+
+      let frame = &buf[FRAME_LEN_PREFIX_BYTES..FRAME_LEN_PREFIX_BYTES + frame_len];
+      let params: CollabParams = bincode::deserialize(frame)?;
+
+      metrics
+        .collab
+        .record_collab_write(&params.collab_type, params.encoded_collab_v1.len());
+      counts_by_type
+        .entry(format!("{:?}", params.collab_type))
+        .or_insert_with(|| (params.collab_type.clone(), 0))
+        .1 += 1;
+      persisted_object_ids.push(params.object_id.clone());
+
+      on_collab_decoded(params).await;
+      buf.drain(..FRAME_LEN_PREFIX_BYTES + frame_len);
+    }
+  }
+
+  if !buf.is_empty() {
+    return Err(StreamDecodeError::Malformed(format!(
+      "{} trailing byte(s) after the last complete frame",
+      buf.len()
+    )));
+  }
+
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::api::metrics::metrics_registry;
+  use futures_util::stream;
+  use std::sync::Mutex;
+
+  fn encode_frame(params: &CollabParams) -> Bytes {
+    let payload = bincode::serialize(params).unwrap();
+    let mut framed = Vec::with_capacity(FRAME_LEN_PREFIX_BYTES + payload.len());
+    framed.extend_from_slice(&(payload.len() as u32).to_le_bytes());
+    framed.extend_from_slice(&payload);
+    Bytes::from(framed)
+  }
+
+  #[tokio::test]
+  async fn decode_collab_stream_round_trip_test() {
+    let params_list = vec![
+      CollabParams {
+        object_id: "object-1".to_string(),
+        encoded_collab_v1: vec![1, 2, 3, 4, 5],
+        collab_type: CollabType::Document,
+        override_if_exist: false,
+      },
+      CollabParams {
+        object_id: "object-2".to_string(),
+        encoded_collab_v1: vec![6, 7, 8],
+        collab_type: CollabType::Document,
+        override_if_exist: false,
+      },
+    ];
+
+    // Split each frame across two wire chunks so the test also exercises the
+    // partial-frame buffering path, not just one chunk per frame.
+    let mut chunks: Vec<Result<Bytes, PayloadError>> = Vec::new();
+    for params in &params_list {
+      let frame = encode_frame(params);
+      let mid = frame.len() / 2;
+      chunks.push(Ok(frame.slice(0..mid)));
+      chunks.push(Ok(frame.slice(mid..)));
+    }
+    let byte_stream = stream::iter(chunks);
+
+    let (metrics, _registry) = metrics_registry();
+    let metrics = Arc::new(metrics);
+
+    let persisted = Arc::new(Mutex::new(Vec::new()));
+    let persisted_for_closure = persisted.clone();
+
+    let count = decode_collab_stream(
+      byte_stream,
+      metrics,
+      move |params| {
+        let persisted = persisted_for_closure.clone();
+        async move {
+          persisted.lock().unwrap().push(params);
+        }
+      },
+      |_object_ids| async {},
+    )
+    .await
+    .unwrap();
+
+    assert_eq!(count, 2);
+    let persisted = persisted.lock().unwrap();
+    assert_eq!(persisted.len(), 2);
+    assert_eq!(persisted[0].object_id, "object-1");
+    assert_eq!(persisted[0].encoded_collab_v1, vec![1, 2, 3, 4, 5]);
+    assert_eq!(persisted[1].object_id, "object-2");
+    assert_eq!(persisted[1].encoded_collab_v1, vec![6, 7, 8]);
+  }
+
+  #[tokio::test]
+  async fn decode_collab_stream_rejects_oversized_frame_length_test() {
+    let mut framed = Vec::new();
+    framed.extend_from_slice(&(MAX_FRAME_LEN as u32 + 1).to_le_bytes());
+    let chunks: Vec<Result<Bytes, PayloadError>> = vec![Ok(Bytes::from(framed))];
+    let byte_stream = stream::iter(chunks);
+
+    let (metrics, _registry) = metrics_registry();
+    let metrics = Arc::new(metrics);
+
+    let result = decode_collab_stream(byte_stream, metrics, |_params| async {}, |_object_ids| async {}).await;
+
+    assert!(matches!(result, Err(StreamDecodeError::Malformed(_))));
+  }
+}
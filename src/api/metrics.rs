@@ -2,13 +2,21 @@ use actix_web::web;
 use actix_web::HttpResponse;
 use actix_web::Result;
 use actix_web::Scope;
+use opentelemetry::trace::TraceContextExt;
 use prometheus_client::encoding::text::encode;
 use prometheus_client::encoding::EncodeLabelSet;
 use prometheus_client::metrics::counter::Counter;
-use prometheus_client::metrics::exemplar::CounterWithExemplar;
+use prometheus_client::metrics::exemplar::{CounterWithExemplar, HistogramWithExemplars};
 use prometheus_client::metrics::family::Family;
+use prometheus_client::metrics::gauge::Gauge;
+use prometheus_client::metrics::histogram::{exponential_buckets, Histogram};
 use prometheus_client::registry::Registry;
+use regex::Regex;
+use std::sync::atomic::AtomicU64;
 use std::sync::Arc;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+
+use collab_entity::CollabType;
 
 pub fn metrics_scope() -> Scope {
   web::scope("/metrics").service(web::resource("").route(web::get().to(metrics_handler)))
@@ -34,6 +42,63 @@ pub fn metrics_registry() -> (AppFlowyCloudMetrics, Registry) {
   (metric, registry)
 }
 
+// Default regex -> route template rewrites applied by `PathNormalizer`. Handlers in this
+// crate embed identifiers like workspace_id/object_id directly in the URL, so without this
+// rewrite `requests_count`/`requests_result` cardinality would grow with the number of
+// objects instead of the number of routes.
+const DEFAULT_PATH_TEMPLATES: &[(&str, &str)] = &[
+  (
+    r"^/api/workspace/[0-9a-fA-F-]{36}/collab/[0-9a-fA-F-]{36}$",
+    "/api/workspace/:workspace_id/collab/:object_id",
+  ),
+  (
+    r"^/api/workspace/[0-9a-fA-F-]{36}/collabs$",
+    "/api/workspace/:workspace_id/collabs",
+  ),
+  (
+    r"^/api/workspace/[0-9a-fA-F-]{36}$",
+    "/api/workspace/:workspace_id",
+  ),
+];
+
+// Catches any UUID-shaped segment that isn't covered by a more specific template above, so
+// an unanticipated route still collapses to a bounded label instead of leaking raw ids.
+const GENERIC_UUID_SEGMENT: &str = r"[0-9a-fA-F]{8}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{4}-[0-9a-fA-F]{12}";
+
+#[derive(Clone)]
+pub struct PathNormalizer {
+  rules: Vec<(Regex, String)>,
+  fallback: Regex,
+}
+
+impl Default for PathNormalizer {
+  fn default() -> Self {
+    let rules = DEFAULT_PATH_TEMPLATES
+      .iter()
+      .map(|(pattern, template)| (Regex::new(pattern).unwrap(), template.to_string()))
+      .collect();
+    Self {
+      rules,
+      fallback: Regex::new(GENERIC_UUID_SEGMENT).unwrap(),
+    }
+  }
+}
+
+impl PathNormalizer {
+  // Rewrites a concrete request path into its route template, e.g.
+  // `/api/workspace/<uuid>/collab/<uuid>` -> `/api/workspace/:workspace_id/collab/:object_id`.
+  // Falls back to collapsing any bare UUID segment into `:id` so unmatched routes still keep
+  // bounded cardinality.
+  pub fn normalize(&self, path: &str) -> String {
+    for (regex, template) in &self.rules {
+      if regex.is_match(path) {
+        return template.clone();
+      }
+    }
+    self.fallback.replace_all(path, ":id").into_owned()
+  }
+}
+
 #[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
 pub struct PathLabel {
   pub path: String,
@@ -45,14 +110,122 @@ pub struct ResultLabel {
   pub status_code: u16,
 }
 
+#[derive(Clone, Debug, Hash, PartialEq, Eq, EncodeLabelSet)]
+pub struct CollabTypeLabel {
+  pub collab_type: String,
+}
+
+impl From<&CollabType> for CollabTypeLabel {
+  fn from(collab_type: &CollabType) -> Self {
+    Self {
+      collab_type: format!("{:?}", collab_type),
+    }
+  }
+}
+
+// Metrics for collab storage writes, kept in their own sub-registry so operators can alarm
+// on abnormal write volume and size skew without wading through the generic HTTP metrics.
+#[derive(Clone)]
+pub struct CollabMetrics {
+  collab_bytes_written: Family<CollabTypeLabel, Counter>,
+  collab_encoded_size_bytes: Family<CollabTypeLabel, Histogram>,
+  // A plain counter of collabs written via batch requests, per the original ask — not a
+  // distribution. If per-request batch-size skew needs to be queryable later, add a
+  // separate histogram rather than changing this counter's semantics.
+  collab_batch_size: Family<CollabTypeLabel, Counter>,
+  // compressed_len / original_len for the most recently transferred payload of this collab
+  // type; a gauge rather than a histogram since operators care about the current ratio
+  // drifting, not its historical distribution.
+  collab_compression_ratio: Family<CollabTypeLabel, Gauge<f64, AtomicU64>>,
+}
+
+impl CollabMetrics {
+  fn init() -> Self {
+    Self {
+      collab_bytes_written: Family::default(),
+      // 1KB to ~8MB, 14 buckets: 1024 * 2^0 ..= 1024 * 2^13
+      collab_encoded_size_bytes: Family::new_with_constructor(|| {
+        Histogram::new(exponential_buckets(1024.0, 2.0, 14))
+      }),
+      collab_batch_size: Family::default(),
+      collab_compression_ratio: Family::default(),
+    }
+  }
+
+  fn register(self, registry: &mut Registry) {
+    let collab_registry = registry.sub_registry_with_prefix("appflowy_cloud_collab");
+    collab_registry.register(
+      "bytes_written",
+      "total bytes of encoded collab written, by collab type",
+      self.collab_bytes_written.clone(),
+    );
+    collab_registry.register(
+      "encoded_size_bytes",
+      "distribution of encoded collab size in bytes, by collab type",
+      self.collab_encoded_size_bytes.clone(),
+    );
+    collab_registry.register(
+      "batch_size",
+      "total number of collabs written via batch requests, by collab type",
+      self.collab_batch_size.clone(),
+    );
+    collab_registry.register(
+      "compression_ratio",
+      "compressed/original byte ratio of the most recently transferred payload, by collab type",
+      self.collab_compression_ratio.clone(),
+    );
+  }
+
+  // Batch/single collab write handlers should call this for every collab persisted so
+  // operators can observe throughput and catch oversized documents.
+  pub fn record_collab_write(&self, collab_type: &CollabType, encoded_len: usize) {
+    let label = CollabTypeLabel::from(collab_type);
+    self
+      .collab_bytes_written
+      .get_or_create(&label)
+      .inc_by(encoded_len as u64);
+    self
+      .collab_encoded_size_bytes
+      .get_or_create(&label)
+      .observe(encoded_len as f64);
+  }
+
+  // Called once per batch write request with the number of collabs of `collab_type` it
+  // contained; callers with a mixed-type batch should call this once per type present.
+  pub fn record_collab_batch(&self, collab_type: &CollabType, batch_size: usize) {
+    self
+      .collab_batch_size
+      .get_or_create(&CollabTypeLabel::from(collab_type))
+      .inc_by(batch_size as u64);
+  }
+
+  // Called when a request negotiated zstd transport compression, with the ratio of
+  // compressed to original payload size (smaller is better compression).
+  pub fn record_compression_ratio(&self, collab_type: &CollabType, ratio: f64) {
+    self
+      .collab_compression_ratio
+      .get_or_create(&CollabTypeLabel::from(collab_type))
+      .set(ratio);
+  }
+}
+
 // Metrics contains list of metrics that are collected by the application.
 // Metric types: https://prometheus.io/docs/concepts/metric_types
 // Application handlers should call the corresponding methods to update the metrics.
 #[derive(Clone)]
 pub struct AppFlowyCloudMetrics {
   requests_count: Family<PathLabel, Counter>,
+  // Deprecated: kept for backward compatibility with existing dashboards. This only
+  // accumulates a running sum of milliseconds and cannot be used to derive percentiles.
+  // Use `requests_duration_seconds` for p50/p95/p99 via `histogram_quantile`.
   requests_latency: Family<PathLabel, CounterWithExemplar<TraceLabel>>,
+  // `HistogramWithExemplars` rather than a plain `Histogram` so a sampled request can attach
+  // its trace id to the bucket it landed in, letting Grafana's exemplars panel jump from a
+  // latency spike straight to the matching distributed trace.
+  requests_duration_seconds: Family<PathLabel, HistogramWithExemplars<TraceLabel>>,
   requests_result: Family<ResultLabel, CounterWithExemplar<TraceLabel>>,
+  path_normalizer: PathNormalizer,
+  pub collab: CollabMetrics,
 }
 
 #[derive(Clone, Hash, PartialEq, Eq, EncodeLabelSet, Debug, Default)]
@@ -60,12 +233,41 @@ pub struct TraceLabel {
   pub trace_id: String,
 }
 
+const W3C_TRACE_ID_HEX_LEN: usize = 32;
+const INVALID_W3C_TRACE_ID: &str = "00000000000000000000000000000000";
+
+fn is_valid_w3c_trace_id(trace_id: &str) -> bool {
+  trace_id.len() == W3C_TRACE_ID_HEX_LEN
+    && trace_id != INVALID_W3C_TRACE_ID
+    && trace_id.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+// Normalizes a caller-supplied trace id into the 32-hex-char W3C `traceparent` trace-id form.
+// When the caller passes `None` (or an invalid id), falls back to the trace id of the active
+// `tracing`/OpenTelemetry span, if any, so exemplars still correlate to a real trace whenever
+// the handler is running inside an instrumented request span.
+fn resolve_trace_id(trace_id: Option<String>) -> Option<String> {
+  let from_caller = trace_id
+    .map(|id| id.trim().to_ascii_lowercase())
+    .filter(|id| is_valid_w3c_trace_id(id));
+  from_caller.or_else(|| {
+    let span_context = tracing::Span::current().context().span().span_context().clone();
+    span_context.is_valid().then(|| span_context.trace_id().to_string())
+  })
+}
+
 impl AppFlowyCloudMetrics {
   fn init() -> Self {
     Self {
       requests_count: Family::default(),
       requests_latency: Family::default(),
+      // ~5ms to ~40s, 14 buckets: 0.005 * 2^0 ..= 0.005 * 2^13
+      requests_duration_seconds: Family::new_with_constructor(|| {
+        HistogramWithExemplars::new(exponential_buckets(0.005, 2.0, 14))
+      }),
       requests_result: Family::default(),
+      path_normalizer: PathNormalizer::default(),
+      collab: CollabMetrics::init(),
     }
   }
 
@@ -81,15 +283,26 @@ impl AppFlowyCloudMetrics {
       "request response time",
       self.requests_latency.clone(),
     );
+    af_registry.register(
+      "requests_duration_seconds",
+      "request response time distribution, use histogram_quantile() for p50/p95/p99",
+      self.requests_duration_seconds.clone(),
+    );
     af_registry.register(
       "requests_result",
       "status code of response",
       self.requests_result.clone(),
     );
+    self.collab.register(registry);
   }
 
-  // app services/middleware should call this method to increase the request count for the path
+  // app services/middleware should call this method to increase the request count for the path.
+  // `path` is expected to be the raw request path; it is normalized into its route template
+  // (e.g. `/api/workspace/:workspace_id/collab/:object_id`) before being used as a label so
+  // metric cardinality stays proportional to the number of routes, not the number of objects.
   pub fn record_request(&self, trace_id: Option<String>, path: String, ms: u64, status_code: u16) {
+    let path = self.path_normalizer.normalize(&path);
+    let trace_id = resolve_trace_id(trace_id);
     self
       .requests_count
       .get_or_create(&PathLabel { path: path.clone() })
@@ -98,6 +311,13 @@ impl AppFlowyCloudMetrics {
       .requests_latency
       .get_or_create(&PathLabel { path: path.clone() })
       .inc_by(ms, trace_id.clone().map(|s| TraceLabel { trace_id: s }));
+    self
+      .requests_duration_seconds
+      .get_or_create(&PathLabel { path: path.clone() })
+      .observe(
+        ms as f64 / 1000.0,
+        trace_id.clone().map(|s| TraceLabel { trace_id: s }),
+      );
     self
       .requests_result
       .get_or_create(&ResultLabel { path, status_code })
@@ -236,6 +236,70 @@ async fn batch_create_collab_compatibility_with_uncompress_params_test() {
   assert_eq!(encoded_collab, encoded_collab_from_server);
 }
 
+#[tokio::test]
+async fn batch_create_collab_zstd_compressed_round_trip_test() {
+  let test_client = TestClient::new_user().await;
+  let workspace_id = test_client.workspace_id().await;
+  let object_id = Uuid::new_v4().to_string();
+  let api_client = &test_client.api_client;
+  let url = format!(
+    "{}/api/workspace/{}/collabs",
+    api_client.base_url, workspace_id,
+  );
+
+  let encoded_collab = EncodedCollab::new_v1(vec![0, 1, 2, 3, 4, 5, 6], vec![7, 8, 9, 10]);
+  let uncompressed = BatchCreateCollabParams {
+    workspace_id: workspace_id.to_string(),
+    params_list: vec![CollabParams {
+      object_id: object_id.clone(),
+      encoded_collab_v1: encoded_collab.encode_to_bytes().unwrap(),
+      collab_type: CollabType::Document,
+      override_if_exist: false,
+    }],
+  }
+  .to_bytes()
+  .unwrap();
+  let compressed = zstd::stream::encode_all(uncompressed.as_slice(), 3).unwrap();
+
+  test_client
+    .api_client
+    .http_client_with_auth(Method::POST, &url)
+    .await
+    .unwrap()
+    .header(reqwest::header::CONTENT_ENCODING, "zstd")
+    .body(compressed)
+    .send()
+    .await
+    .unwrap();
+
+  let url = format!(
+    "{}/api/workspace/{}/collab/{}",
+    api_client.base_url, workspace_id, &object_id
+  );
+  let resp = test_client
+    .api_client
+    .http_client_with_auth(Method::GET, &url)
+    .await
+    .unwrap()
+    .json(&QueryCollabParams {
+      workspace_id,
+      inner: QueryCollab {
+        object_id: object_id.clone(),
+        collab_type: CollabType::Document,
+      },
+    })
+    .send()
+    .await
+    .unwrap();
+
+  let encoded_collab_from_server = AppResponse::<EncodedCollab>::from_response(resp)
+    .await
+    .unwrap()
+    .into_data()
+    .unwrap();
+  assert_eq!(encoded_collab, encoded_collab_from_server);
+}
+
 #[derive(Debug, Clone, Serialize)]
 pub struct OldCreateCollabParams {
   #[serde(flatten)]